@@ -0,0 +1,256 @@
+//! Compressed bitmap used internally to back [`DataFilter`](crate::DataFilter).
+//!
+//! The index space is partitioned into fixed-size blocks of [`BLOCK_BITS`]
+//! positions. A block holding few set bits is stored as a sorted array of
+//! 16-bit offsets; once its cardinality crosses [`DENSE_THRESHOLD`] it is
+//! promoted to a dense bit array instead, mirroring the sparse/dense
+//! container split used by roaring bitmaps so that both storage and set
+//! algebra stay cheap regardless of how selective a filter is.
+
+use std::collections::BTreeMap;
+
+use iter_set::{intersection, union};
+
+pub(crate) const BLOCK_BITS: usize = 1 << 16;
+const BLOCK_WORDS: usize = BLOCK_BITS / 64;
+const DENSE_THRESHOLD: usize = 4096;
+
+#[derive(Clone)]
+enum Block {
+    Sparse(Vec<u16>),
+    Dense(Box<[u64; BLOCK_WORDS]>),
+}
+
+impl Block {
+    fn empty() -> Self {
+        Block::Sparse(Vec::new())
+    }
+
+    fn from_sorted_offsets(offsets: Vec<u16>) -> Self {
+        if offsets.len() > DENSE_THRESHOLD {
+            Block::Dense(Self::dense_from_offsets(&offsets))
+        } else {
+            Block::Sparse(offsets)
+        }
+    }
+
+    fn dense_from_offsets(offsets: &[u16]) -> Box<[u64; BLOCK_WORDS]> {
+        let mut words = Box::new([0u64; BLOCK_WORDS]);
+        for off in offsets {
+            words[*off as usize / 64] |= 1 << (*off as usize % 64);
+        }
+        words
+    }
+
+    fn to_dense(&self) -> Box<[u64; BLOCK_WORDS]> {
+        match self {
+            Block::Dense(words) => words.clone(),
+            Block::Sparse(offsets) => Self::dense_from_offsets(offsets),
+        }
+    }
+
+    /// Builds a block from raw words, demoting it back to the sparse
+    /// representation when the result turned out to be small after all.
+    fn from_dense(words: Box<[u64; BLOCK_WORDS]>) -> Self {
+        let count: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+        if count > DENSE_THRESHOLD {
+            return Block::Dense(words);
+        }
+        let mut offsets = Vec::with_capacity(count);
+        for (word_index, word) in words.iter().enumerate() {
+            let mut word = *word;
+            while word != 0 {
+                let bit = word.trailing_zeros() as usize;
+                offsets.push((word_index * 64 + bit) as u16);
+                word &= word - 1;
+            }
+        }
+        Block::Sparse(offsets)
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Block::Sparse(offsets) => offsets.len(),
+            Block::Dense(words) => words.iter().map(|w| w.count_ones() as usize).sum(),
+        }
+    }
+
+    fn contains(&self, offset: u16) -> bool {
+        match self {
+            Block::Sparse(offsets) => offsets.binary_search(&offset).is_ok(),
+            Block::Dense(words) => words[offset as usize / 64] & (1 << (offset as usize % 64)) != 0,
+        }
+    }
+
+    fn and(&self, other: &Block) -> Block {
+        match (self, other) {
+            (Block::Sparse(a), Block::Sparse(b)) => {
+                Block::Sparse(intersection(a.iter().cloned(), b.iter().cloned()).collect())
+            }
+            _ => {
+                let a = self.to_dense();
+                let b = other.to_dense();
+                let mut words = Box::new([0u64; BLOCK_WORDS]);
+                for i in 0..BLOCK_WORDS {
+                    words[i] = a[i] & b[i];
+                }
+                Block::from_dense(words)
+            }
+        }
+    }
+
+    fn or(&self, other: &Block) -> Block {
+        match (self, other) {
+            (Block::Sparse(a), Block::Sparse(b)) => {
+                Block::from_sorted_offsets(union(a.iter().cloned(), b.iter().cloned()).collect())
+            }
+            _ => {
+                let a = self.to_dense();
+                let b = other.to_dense();
+                let mut words = Box::new([0u64; BLOCK_WORDS]);
+                for i in 0..BLOCK_WORDS {
+                    words[i] = a[i] | b[i];
+                }
+                Block::from_dense(words)
+            }
+        }
+    }
+
+    /// Complements the block against `valid_bits` positions, masking off
+    /// whatever tail of the block falls outside the data store's range.
+    fn not(&self, valid_bits: usize) -> Block {
+        let mut words = self.to_dense();
+        for word in words.iter_mut() {
+            *word = !*word;
+        }
+        if valid_bits < BLOCK_BITS {
+            let full_words = valid_bits / 64;
+            let remaining_bits = valid_bits % 64;
+            if remaining_bits > 0 {
+                words[full_words] &= (1u64 << remaining_bits) - 1;
+                for word in &mut words[full_words + 1..] {
+                    *word = 0;
+                }
+            } else {
+                for word in &mut words[full_words..] {
+                    *word = 0;
+                }
+            }
+        }
+        Block::from_dense(words)
+    }
+
+    /// Consumes the block, yielding its set offsets in ascending order.
+    fn into_offsets(self) -> Box<dyn Iterator<Item = u16>> {
+        match self {
+            Block::Sparse(offsets) => Box::new(offsets.into_iter()),
+            Block::Dense(words) => Box::new((*words).into_iter().enumerate().flat_map(
+                |(word_index, word)| {
+                    let mut word = word;
+                    std::iter::from_fn(move || {
+                        if word == 0 {
+                            None
+                        } else {
+                            let bit = word.trailing_zeros() as usize;
+                            word &= word - 1;
+                            Some((word_index * 64 + bit) as u16)
+                        }
+                    })
+                },
+            )),
+        }
+    }
+}
+
+/// A sorted set of `usize` indices, stored as sparse/dense blocks of
+/// [`BLOCK_BITS`] positions each.
+#[derive(Clone, Default)]
+pub(crate) struct ChunkedBitmap {
+    blocks: BTreeMap<usize, Block>,
+}
+
+impl ChunkedBitmap {
+    pub(crate) fn from_sorted_indices<I>(indices: I) -> Self
+    where
+        I: Iterator<Item = usize>,
+    {
+        let mut by_block: BTreeMap<usize, Vec<u16>> = BTreeMap::new();
+        for index in indices {
+            let block_id = index / BLOCK_BITS;
+            let offset = (index % BLOCK_BITS) as u16;
+            by_block.entry(block_id).or_default().push(offset);
+        }
+        Self {
+            blocks: by_block
+                .into_iter()
+                .map(|(block_id, offsets)| (block_id, Block::from_sorted_offsets(offsets)))
+                .collect(),
+        }
+    }
+
+    /// Returns whether `index` is set in the bitmap.
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        let block_id = index / BLOCK_BITS;
+        let offset = (index % BLOCK_BITS) as u16;
+        match self.blocks.get(&block_id) {
+            Some(block) => block.contains(offset),
+            None => false,
+        }
+    }
+
+    /// Consumes the bitmap, yielding the set indices in ascending order.
+    pub(crate) fn into_iter(self) -> impl Iterator<Item = usize> {
+        self.blocks.into_iter().flat_map(|(block_id, block)| {
+            let base = block_id * BLOCK_BITS;
+            block
+                .into_offsets()
+                .map(move |offset| base + offset as usize)
+        })
+    }
+
+    pub(crate) fn and(&self, other: &Self) -> Self {
+        let mut blocks = BTreeMap::new();
+        for (block_id, block) in &self.blocks {
+            if let Some(other_block) = other.blocks.get(block_id) {
+                let merged = block.and(other_block);
+                if merged.len() > 0 {
+                    blocks.insert(*block_id, merged);
+                }
+            }
+        }
+        Self { blocks }
+    }
+
+    pub(crate) fn or(&self, other: &Self) -> Self {
+        let mut blocks = self.blocks.clone();
+        for (block_id, block) in &other.blocks {
+            blocks
+                .entry(*block_id)
+                .and_modify(|existing| *existing = existing.or(block))
+                .or_insert_with(|| block.clone());
+        }
+        Self { blocks }
+    }
+
+    /// Complements the bitmap against a universe of `universe_len` indices
+    /// (`0..universe_len`).
+    pub(crate) fn not(&self, universe_len: usize) -> Self {
+        let block_count = universe_len.div_ceil(BLOCK_BITS);
+        let mut blocks = BTreeMap::new();
+        for block_id in 0..block_count {
+            let valid_bits = if block_id + 1 == block_count {
+                universe_len - block_id * BLOCK_BITS
+            } else {
+                BLOCK_BITS
+            };
+            let complemented = match self.blocks.get(&block_id) {
+                Some(block) => block.not(valid_bits),
+                None => Block::empty().not(valid_bits),
+            };
+            if complemented.len() > 0 {
+                blocks.insert(block_id, complemented);
+            }
+        }
+        Self { blocks }
+    }
+}
@@ -23,68 +23,347 @@
 //!     .collect();
 //! ```
 use std::{
-    collections::BTreeMap,
-    ops::{BitAnd, BitOr, Bound::*, RangeBounds},
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    ops::{Add, BitAnd, BitOr, Bound::*, Not, RangeBounds},
+    rc::{Rc, Weak},
 };
 
-use iter_set::{intersection, union};
+mod bitmap;
+use bitmap::ChunkedBitmap;
+
+///Direction to walk a [SortedIndex](SortedIndex) in, used by
+///[QueriableDataStore::filter_ordered] and [QueriableDataStore::top_n].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Order {
+    Ascending,
+    Descending,
+}
 
 ///Data structure that can be queried by multiple filters.
-///Its not allowed to modify data after the generation of the data store.
+///Items can be added with [insert](QueriableDataStore::insert) and removed with
+///[remove](QueriableDataStore::remove) after the data store has been created.
+///[Index](SortedIndex)es obtained with [get_index](QueriableDataStore::get_index) are
+///point-in-time snapshots; use [get_live_index](QueriableDataStore::get_live_index) to keep an
+///index up to date incrementally across future inserts/removes instead of rebuilding it.
 pub struct QueriableDataStore<T> {
-    items: Vec<T>,
+    items: Vec<Option<T>>,
+    len: Rc<Cell<usize>>,
+    index_updaters: RefCell<Vec<Box<dyn IndexUpdater<T>>>>,
 }
 
 impl<T> QueriableDataStore<T> {
     ///Get all entries of the [DataStore](QueriableDataStore) that match the filter.
     pub fn filter(&self, filter: DataFilter) -> impl Iterator<Item = &T> {
-        filter.indices.into_iter().map(move |v| &self.items[v])
+        filter
+            .bitmap
+            .into_iter()
+            .filter_map(move |v| self.items[v].as_ref())
     }
 
-    ///Get a new [Index](SortedIndex) for the [DataStore](QueriableDataStore) for the provided key.
+    ///Get all entries of the [DataStore](QueriableDataStore) that match `filter`, ordered by
+    ///`index`'s key instead of by storage position.
+    pub fn filter_ordered<K>(
+        &self,
+        filter: DataFilter,
+        index: &SortedIndex<K>,
+        order: Order,
+    ) -> impl Iterator<Item = &T>
+    where
+        K: Ord,
+    {
+        let pairs = index.pairs.borrow();
+        let matched: Vec<usize> = match order {
+            Order::Ascending => pairs
+                .values()
+                .flat_map(|bucket| bucket.iter().cloned())
+                .filter(|i| filter.contains(*i))
+                .collect(),
+            Order::Descending => pairs
+                .values()
+                .rev()
+                .flat_map(|bucket| bucket.iter().cloned())
+                .filter(|i| filter.contains(*i))
+                .collect(),
+        };
+        matched
+            .into_iter()
+            .filter_map(move |i| self.items[i].as_ref())
+    }
+
+    ///Get the `n` entries of the [DataStore](QueriableDataStore) that match `filter` with the
+    ///smallest (for [Order::Ascending]) or largest (for [Order::Descending]) `index` key, using a
+    ///bounded binary heap of size `n` instead of sorting every match.
+    pub fn top_n<K>(
+        &self,
+        filter: DataFilter,
+        index: &SortedIndex<K>,
+        n: usize,
+        order: Order,
+    ) -> impl Iterator<Item = &T>
+    where
+        K: Ord + Clone,
+    {
+        let mut selected: Vec<(K, usize)> = Vec::new();
+        if n > 0 {
+            let pairs = index.pairs.borrow();
+            let candidates = pairs.iter().flat_map(|(key, bucket)| {
+                bucket
+                    .iter()
+                    .filter(|i| filter.contains(**i))
+                    .map(move |&i| (key.clone(), i))
+            });
+            selected = match order {
+                Order::Ascending => {
+                    let mut heap: BinaryHeap<(K, usize)> = BinaryHeap::new();
+                    for candidate in candidates {
+                        heap.push(candidate);
+                        if heap.len() > n {
+                            heap.pop();
+                        }
+                    }
+                    let mut selected: Vec<(K, usize)> = heap.into_vec();
+                    selected.sort();
+                    selected
+                }
+                Order::Descending => {
+                    let mut heap: BinaryHeap<Reverse<(K, usize)>> = BinaryHeap::new();
+                    for candidate in candidates {
+                        heap.push(Reverse(candidate));
+                        if heap.len() > n {
+                            heap.pop();
+                        }
+                    }
+                    let mut selected: Vec<(K, usize)> =
+                        heap.into_iter().map(|Reverse(c)| c).collect();
+                    selected.sort_by(|a, b| b.cmp(a));
+                    selected
+                }
+            };
+        }
+        selected
+            .into_iter()
+            .filter_map(move |(_, i)| self.items[i].as_ref())
+    }
+
+    ///Get a new, point-in-time [Index](SortedIndex) for the [DataStore](QueriableDataStore) for
+    ///the provided key. The index reflects the store's contents at the time of this call; it
+    ///will not see items inserted or removed afterwards. Use
+    ///[get_live_index](QueriableDataStore::get_live_index) if the index needs to track the store
+    ///as it mutates.
     pub fn get_index<F, U>(&self, index_provider: F) -> SortedIndex<U>
     where
         F: Fn(&T) -> U,
         U: Ord,
+    {
+        SortedIndex::from_snapshot(self, index_provider)
+    }
+
+    ///Get a new [Index](SortedIndex) that keeps itself up to date incrementally instead of needing
+    ///to be rebuilt: O(log k) per [insert](QueriableDataStore::insert) and O(log k + m) per
+    ///[remove](QueriableDataStore::remove), where k is the number of distinct keys in the index
+    ///and m is the number of items sharing the removed item's key. Since the store
+    ///keeps `index_provider` around to re-run on every future mutation, it must be `'static`;
+    ///use [get_index](QueriableDataStore::get_index) for a cheaper, non-capturing snapshot if the
+    ///index doesn't need to track mutations.
+    pub fn get_live_index<F, U>(&self, index_provider: F) -> SortedIndex<U>
+    where
+        F: Fn(&T) -> U + 'static,
+        U: Ord + 'static,
     {
         SortedIndex::new(self, index_provider)
     }
 
     ///Iterate over all items in the [DataStore](QueriableDataStore).
     pub fn items(&self) -> impl Iterator<Item = &T> {
-        self.items.iter()
+        self.items.iter().filter_map(|v| v.as_ref())
+    }
+
+    ///Appends `item` to the [DataStore](QueriableDataStore) and updates every live
+    ///[Index](SortedIndex) incrementally (O(log k) per index, where k is the number of distinct
+    ///keys in that index) instead of rebuilding it. Returns the index `item` was stored at.
+    pub fn insert(&mut self, item: T) -> usize {
+        let index = self.items.len();
+        self.index_updaters
+            .borrow_mut()
+            .retain(|updater| updater.on_insert(&item, index));
+        self.items.push(Some(item));
+        self.len.set(self.items.len());
+        index
+    }
+
+    ///Removes the item at `index`, tombstoning its slot so every other item keeps its index
+    ///stable, and updates every live [Index](SortedIndex) incrementally. Removing an index that
+    ///was already removed, or that is out of bounds, is a no-op.
+    pub fn remove(&mut self, index: usize) {
+        if let Some(slot) = self.items.get_mut(index) {
+            if let Some(item) = slot.take() {
+                self.index_updaters
+                    .borrow_mut()
+                    .retain(|updater| updater.on_remove(&item, index));
+            }
+        }
+    }
+
+    ///Get a [GroupedQuery](GroupedQuery) that aggregates this store's items per distinct key of
+    ///`index`.
+    pub fn group_by<K>(&self, index: &SortedIndex<K>) -> GroupedQuery<'_, T, K>
+    where
+        K: Ord + Clone,
+    {
+        GroupedQuery {
+            store: self,
+            index: index.clone(),
+        }
     }
 }
 
 impl<T> From<Vec<T>> for QueriableDataStore<T> {
     fn from(items: Vec<T>) -> Self {
-        Self { items }
+        let len = items.len();
+        Self {
+            items: items.into_iter().map(Some).collect(),
+            len: Rc::new(Cell::new(len)),
+            index_updaters: RefCell::new(Vec::new()),
+        }
     }
 }
 
-///Index of a [DataStore](QueriableDataStore).
-#[derive(Clone, Eq, PartialEq)]
+///Receives incremental updates from a [DataStore](QueriableDataStore) for as long as the
+///[Index](SortedIndex) it belongs to is still alive.
+trait IndexUpdater<T> {
+    ///Returns `false` once the backing index has been dropped, so the store can forget it.
+    fn on_insert(&self, item: &T, index: usize) -> bool;
+    ///Returns `false` once the backing index has been dropped, so the store can forget it.
+    fn on_remove(&self, item: &T, index: usize) -> bool;
+}
+
+struct RegisteredIndex<U, F> {
+    pairs: Weak<RefCell<BTreeMap<U, Vec<usize>>>>,
+    index_provider: F,
+}
+
+impl<T, U, F> IndexUpdater<T> for RegisteredIndex<U, F>
+where
+    F: Fn(&T) -> U,
+    U: Ord,
+{
+    fn on_insert(&self, item: &T, index: usize) -> bool {
+        match self.pairs.upgrade() {
+            Some(pairs) => {
+                let key = (self.index_provider)(item);
+                pairs.borrow_mut().entry(key).or_default().push(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn on_remove(&self, item: &T, index: usize) -> bool {
+        match self.pairs.upgrade() {
+            Some(pairs) => {
+                let key = (self.index_provider)(item);
+                let mut pairs = pairs.borrow_mut();
+                if let Some(bucket) = pairs.get_mut(&key) {
+                    if let Ok(position) = bucket.binary_search(&index) {
+                        bucket.remove(position);
+                    }
+                    if bucket.is_empty() {
+                        pairs.remove(&key);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+///The size of the universe a [SortedIndex](SortedIndex) derives [DataFilter](DataFilter)s
+///against: frozen at snapshot time for [get_index](QueriableDataStore::get_index), or tracking
+///the store's live length for [get_live_index](QueriableDataStore::get_live_index).
+#[derive(Clone)]
+enum IndexLen {
+    Snapshot(usize),
+    Live(Rc<Cell<usize>>),
+}
+
+impl IndexLen {
+    fn get(&self) -> usize {
+        match self {
+            IndexLen::Snapshot(len) => *len,
+            IndexLen::Live(len) => len.get(),
+        }
+    }
+}
+
+///Index of a [DataStore](QueriableDataStore). Cloning an [Index](SortedIndex) is cheap: the
+///clone shares the same underlying data, including any incremental updates if the original was
+///obtained from [get_live_index](QueriableDataStore::get_live_index).
+#[derive(Clone)]
 pub struct SortedIndex<T> {
-    pairs: BTreeMap<T, Vec<usize>>,
+    pairs: Rc<RefCell<BTreeMap<T, Vec<usize>>>>,
+    store_len: IndexLen,
 }
 
 impl<T> SortedIndex<T>
 where
     T: Ord,
 {
-    ///Creates a new [Index](SortedIndex) from a [DataStore](QueriableDataStore) for the index provided by the index_provider function.
-    pub fn new<F, U>(data_store: &QueriableDataStore<U>, index_provider: F) -> Self
+    ///Builds the key-to-indices map for `index_provider` over `data_store`'s current contents.
+    fn build_pairs<F, U>(
+        data_store: &QueriableDataStore<U>,
+        index_provider: &F,
+    ) -> BTreeMap<T, Vec<usize>>
     where
         F: Fn(&U) -> T,
     {
         let mut pairs: BTreeMap<T, Vec<usize>> = BTreeMap::new();
+        for (index, item) in data_store.items.iter().enumerate() {
+            if let Some(item) = item {
+                let key = index_provider(item);
+                pairs.entry(key).or_default().push(index);
+            }
+        }
+        pairs
+    }
 
-        for (index, item) in data_store.items().enumerate() {
-            let key = index_provider(item);
-            pairs.entry(key).or_insert_with(|| vec![]).push(index);
+    ///Creates a point-in-time [Index](SortedIndex) snapshot from a
+    ///[DataStore](QueriableDataStore) for the key provided by `index_provider`. The snapshot does
+    ///not register for incremental updates, so it doesn't need `index_provider` to be `'static`.
+    fn from_snapshot<F, U>(data_store: &QueriableDataStore<U>, index_provider: F) -> Self
+    where
+        F: Fn(&U) -> T,
+    {
+        let pairs = Self::build_pairs(data_store, &index_provider);
+        Self {
+            pairs: Rc::new(RefCell::new(pairs)),
+            store_len: IndexLen::Snapshot(data_store.len.get()),
         }
+    }
+
+    ///Creates a new, incrementally-updating [Index](SortedIndex) from a
+    ///[DataStore](QueriableDataStore) for the index provided by the index_provider function.
+    pub(crate) fn new<F, U>(data_store: &QueriableDataStore<U>, index_provider: F) -> Self
+    where
+        F: Fn(&U) -> T + 'static,
+        T: 'static,
+    {
+        let pairs = Self::build_pairs(data_store, &index_provider);
+        let pairs = Rc::new(RefCell::new(pairs));
+        data_store
+            .index_updaters
+            .borrow_mut()
+            .push(Box::new(RegisteredIndex {
+                pairs: Rc::downgrade(&pairs),
+                index_provider,
+            }));
 
-        Self { pairs }
+        Self {
+            pairs,
+            store_len: IndexLen::Live(Rc::clone(&data_store.len)),
+        }
     }
 
     ///Get a new [DataFilter](DataFilter) for all items in the given range.
@@ -92,12 +371,11 @@ where
     where
         R: RangeBounds<T>,
     {
-        let filtered = self
-            .pairs
+        let pairs = self.pairs.borrow();
+        let filtered = pairs
             .range(range)
-            .into_iter()
             .flat_map(|(_, indices)| indices.iter().cloned());
-        DataFilter::from_unsorted(filtered)
+        DataFilter::from_unsorted(filtered, self.store_len.get())
     }
 
     ///Get a new [DataFilter](DataFilter) for all items between the given values (including lower and upper value).
@@ -107,10 +385,11 @@ where
 
     ///Get a new [DataFilter](DataFilter) for all items that are equivalent to the given value.
     pub fn filter_eq(&self, value: T) -> DataFilter {
-        if let Some(keys) = self.pairs.get(&value) {
-            DataFilter::from_unsorted(keys.iter().cloned())
+        let pairs = self.pairs.borrow();
+        if let Some(keys) = pairs.get(&value) {
+            DataFilter::from_unsorted(keys.iter().cloned(), self.store_len.get())
         } else {
-            DataFilter::default()
+            DataFilter::empty(self.store_len.get())
         }
     }
 
@@ -133,24 +412,268 @@ where
     pub fn filter_lte(&self, upper_limit: T) -> DataFilter {
         self.filter_range((Unbounded, Included(upper_limit)))
     }
+
+    ///Get a new [DataFilter](DataFilter) for all items with the smallest key.
+    pub fn first(&self) -> DataFilter {
+        self.first_n(1)
+    }
+
+    ///Get a new [DataFilter](DataFilter) for all items belonging to the `n` smallest keys.
+    pub fn first_n(&self, n: usize) -> DataFilter {
+        let pairs = self.pairs.borrow();
+        let filtered = pairs
+            .values()
+            .take(n)
+            .flat_map(|indices| indices.iter().cloned());
+        DataFilter::from_unsorted(filtered, self.store_len.get())
+    }
+
+    ///Get a new [DataFilter](DataFilter) for all items with the largest key.
+    pub fn last(&self) -> DataFilter {
+        self.last_n(1)
+    }
+
+    ///Get a new [DataFilter](DataFilter) for all items belonging to the `n` largest keys.
+    pub fn last_n(&self, n: usize) -> DataFilter {
+        let pairs = self.pairs.borrow();
+        let filtered = pairs
+            .values()
+            .rev()
+            .take(n)
+            .flat_map(|indices| indices.iter().cloned());
+        DataFilter::from_unsorted(filtered, self.store_len.get())
+    }
+}
+
+impl SortedIndex<String> {
+    ///Get a new [DataFilter](DataFilter) for all keys that start with the given prefix.
+    pub fn filter_prefix(&self, prefix: String) -> DataFilter {
+        match Self::prefix_upper_bound(&prefix) {
+            Some(upper) => self.filter_range((Included(prefix), Excluded(upper))),
+            None => self.filter_range((Included(prefix), Unbounded)),
+        }
+    }
+
+    ///Get a new [DataFilter](DataFilter) for all keys within `max_edits` Levenshtein edits of `term`.
+    ///Walks the sorted key space depth-first, extending one character at a time and pruning any
+    ///subtree whose edit-distance row can no longer reach an accepting state within `max_edits`.
+    pub fn filter_fuzzy(&self, term: &str, max_edits: u8) -> DataFilter {
+        let term: Vec<char> = term.chars().collect();
+        let max_edits = max_edits as usize;
+        let root_row: Vec<usize> = (0..=term.len()).collect();
+        let mut matches = Vec::new();
+        self.fuzzy_walk(String::new(), &root_row, &term, max_edits, &mut matches);
+        let pairs = self.pairs.borrow();
+        let indices: Vec<usize> = matches
+            .into_iter()
+            .flat_map(|key| pairs.get(&key).cloned().unwrap_or_default())
+            .collect();
+        DataFilter::from_unsorted(indices.into_iter(), self.store_len.get())
+    }
+
+    fn fuzzy_walk(
+        &self,
+        prefix: String,
+        row: &[usize],
+        term: &[char],
+        max_edits: usize,
+        matches: &mut Vec<String>,
+    ) {
+        if *row.iter().min().unwrap() > max_edits {
+            return;
+        }
+        if row.last().copied().unwrap_or(usize::MAX) <= max_edits
+            && self.pairs.borrow().contains_key(&prefix)
+        {
+            matches.push(prefix.clone());
+        }
+        for next_char in self.child_chars(&prefix) {
+            let mut child = prefix.clone();
+            child.push(next_char);
+            let next_row = Self::next_row(row, term, next_char);
+            self.fuzzy_walk(child, &next_row, term, max_edits, matches);
+        }
+    }
+
+    ///Finds the distinct characters that directly follow `prefix` among the indexed keys, in order.
+    fn child_chars(&self, prefix: &str) -> Vec<char> {
+        let prefix_len = prefix.chars().count();
+        let mut chars = Vec::new();
+        for (key, _) in self.pairs.borrow().range(prefix.to_string()..) {
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if let Some(c) = key.chars().nth(prefix_len) {
+                if chars.last() != Some(&c) {
+                    chars.push(c);
+                }
+            }
+        }
+        chars
+    }
+
+    ///Computes the next row of the Levenshtein DP table after appending `next_char`.
+    fn next_row(prev_row: &[usize], term: &[char], next_char: char) -> Vec<usize> {
+        let mut row = Vec::with_capacity(prev_row.len());
+        row.push(prev_row[0] + 1);
+        for (i, &term_char) in term.iter().enumerate() {
+            let substitution_cost = if term_char == next_char { 0 } else { 1 };
+            let value = (prev_row[i] + substitution_cost)
+                .min(row[i] + 1)
+                .min(prev_row[i + 1] + 1);
+            row.push(value);
+        }
+        row
+    }
+
+    ///Smallest string strictly greater than every string with `prefix` as a prefix, or `None` if
+    ///`prefix` is empty or made up entirely of `char::MAX`.
+    fn prefix_upper_bound(prefix: &str) -> Option<String> {
+        let mut chars: Vec<char> = prefix.chars().collect();
+        while let Some(last) = chars.pop() {
+            //U+D7FF is followed by the surrogate gap; the next valid `char` is U+E000.
+            let next = if last == '\u{D7FF}' {
+                Some('\u{E000}')
+            } else {
+                char::from_u32(last as u32 + 1)
+            };
+            if let Some(incremented) = next {
+                chars.push(incremented);
+                return Some(chars.into_iter().collect());
+            }
+        }
+        None
+    }
+}
+
+///A per-key aggregation view over a [DataStore](QueriableDataStore), grouped by the distinct
+///keys of a [SortedIndex](SortedIndex). Obtained from [QueriableDataStore::group_by].
+pub struct GroupedQuery<'a, T, K> {
+    store: &'a QueriableDataStore<T>,
+    index: SortedIndex<K>,
+}
+
+impl<'a, T, K> GroupedQuery<'a, T, K>
+where
+    K: Ord + Clone,
+{
+    ///Folds the items of every group with `fold`, starting from `init`, optionally restricting
+    ///the items considered to those also matched by `filter`. Groups left empty by `filter` are
+    ///omitted from the result.
+    pub fn fold_by<A, F>(&self, init: A, fold: F, filter: Option<&DataFilter>) -> BTreeMap<K, A>
+    where
+        A: Clone,
+        F: Fn(A, &'a T) -> A,
+    {
+        let pairs = self.index.pairs.borrow();
+        pairs
+            .iter()
+            .filter_map(|(key, indices)| {
+                let mut acc = init.clone();
+                let mut matched = false;
+                for &index in indices {
+                    if filter.is_none_or(|f| f.contains(index)) {
+                        if let Some(item) = self.store.items[index].as_ref() {
+                            acc = fold(acc, item);
+                            matched = true;
+                        }
+                    }
+                }
+                matched.then(|| (key.clone(), acc))
+            })
+            .collect()
+    }
+
+    ///Counts the items in every group, optionally restricted to those also matched by `filter`.
+    pub fn counts(&self, filter: Option<&DataFilter>) -> BTreeMap<K, usize> {
+        self.fold_by(0, |acc, _| acc + 1, filter)
+    }
+
+    ///Sums `value` over the items of every group, optionally restricted to those also matched by
+    ///`filter`.
+    pub fn sum_by<N, F>(&self, value: F, filter: Option<&DataFilter>) -> BTreeMap<K, N>
+    where
+        F: Fn(&'a T) -> N,
+        N: Add<Output = N> + Default + Clone,
+    {
+        self.fold_by(N::default(), move |acc, item| acc + value(item), filter)
+    }
+
+    ///Finds the item that minimizes `key` in every group, optionally restricted to those also
+    ///matched by `filter`.
+    pub fn min_by<F, O>(&self, key: F, filter: Option<&DataFilter>) -> BTreeMap<K, &'a T>
+    where
+        F: Fn(&'a T) -> O,
+        O: Ord,
+    {
+        self.fold_by(
+            None,
+            move |acc: Option<&'a T>, item| match acc {
+                Some(current) if key(current) <= key(item) => Some(current),
+                _ => Some(item),
+            },
+            filter,
+        )
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k, v)))
+        .collect()
+    }
+
+    ///Finds the item that maximizes `key` in every group, optionally restricted to those also
+    ///matched by `filter`.
+    pub fn max_by<F, O>(&self, key: F, filter: Option<&DataFilter>) -> BTreeMap<K, &'a T>
+    where
+        F: Fn(&'a T) -> O,
+        O: Ord,
+    {
+        self.fold_by(
+            None,
+            move |acc: Option<&'a T>, item| match acc {
+                Some(current) if key(current) >= key(item) => Some(current),
+                _ => Some(item),
+            },
+            filter,
+        )
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k, v)))
+        .collect()
+    }
 }
 
 ///Contains all items that match a given filter.
-///Can be combined with the bitwise logical operators (& |).
-#[derive(Default)]
+///Backed by a compressed bitmap of matching indices for cheap set algebra.
+///Can be combined with the bitwise logical operators (& | !).
+#[derive(Clone, Default)]
 pub struct DataFilter {
-    indices: Vec<usize>,
+    bitmap: ChunkedBitmap,
+    store_len: usize,
 }
 
 impl DataFilter {
-    ///Creates a [DataFilter](DataFilter) from an unsorted list of indices.
-    fn from_unsorted<T>(unsorted_indices: T) -> Self
+    ///Creates a [DataFilter](DataFilter) from an unsorted list of indices into a store of `store_len` items.
+    fn from_unsorted<T>(unsorted_indices: T, store_len: usize) -> Self
     where
         T: Iterator<Item = usize>,
     {
         let mut indices: Vec<usize> = unsorted_indices.collect();
         indices.sort();
-        Self { indices }
+        Self {
+            bitmap: ChunkedBitmap::from_sorted_indices(indices.into_iter()),
+            store_len,
+        }
+    }
+
+    ///Creates an empty [DataFilter](DataFilter) that still knows the size of the store it was derived from.
+    fn empty(store_len: usize) -> Self {
+        Self {
+            bitmap: ChunkedBitmap::default(),
+            store_len,
+        }
+    }
+
+    ///Returns whether `index` is one of this filter's matches.
+    fn contains(&self, index: usize) -> bool {
+        self.bitmap.contains(index)
     }
 }
 
@@ -159,7 +682,8 @@ impl BitAnd for DataFilter {
 
     fn bitand(self, other: DataFilter) -> Self::Output {
         Self {
-            indices: intersection(self.indices, other.indices).collect(),
+            bitmap: self.bitmap.and(&other.bitmap),
+            store_len: self.store_len.max(other.store_len),
         }
     }
 }
@@ -169,7 +693,20 @@ impl BitOr for DataFilter {
 
     fn bitor(self, other: DataFilter) -> Self::Output {
         Self {
-            indices: union(self.indices, other.indices).collect(),
+            bitmap: self.bitmap.or(&other.bitmap),
+            store_len: self.store_len.max(other.store_len),
+        }
+    }
+}
+
+impl Not for DataFilter {
+    type Output = DataFilter;
+
+    ///Complements the filter: matches every item of the originating store that this filter did not match.
+    fn not(self) -> Self::Output {
+        Self {
+            bitmap: self.bitmap.not(self.store_len),
+            store_len: self.store_len,
         }
     }
 }
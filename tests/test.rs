@@ -1,4 +1,4 @@
-use queriable_storage::QueriableDataStore;
+use queriable_storage::{Order, QueriableDataStore};
 
 struct Person {
     first_name: &'static str,
@@ -171,6 +171,217 @@ fn test_combined() {
     assert_eq!(filtered.len(), 2);
 }
 
+#[test]
+fn test_not() {
+    let data = get_test_data();
+    let age_index = data.get_index(|v| v.age);
+    let filtered: Vec<&Person> = data.filter(!age_index.filter_lt(30)).collect();
+    assert_eq!(filtered.len(), 6);
+    assert!(filtered.iter().all(|p| p.age >= 30));
+}
+
+///`Not` complements against the bitmap's block-aligned tail mask, so exercise it against a
+///store whose length straddles a block boundary (`BLOCK_BITS` is 65536) instead of just the
+///10-item fixture used elsewhere in this file.
+#[test]
+fn test_not_across_block_boundary() {
+    let values: Vec<u32> = (0..70_000).collect();
+    let data: QueriableDataStore<u32> = values.into();
+    let value_index = data.get_index(|v| *v);
+
+    let low_half = value_index.filter_lt(40_000);
+    let high_half: Vec<&u32> = data.filter(!low_half).collect();
+    assert_eq!(high_half.len(), 30_000);
+    assert!(high_half.iter().all(|v| **v >= 40_000));
+    assert!(high_half.iter().any(|v| **v == 65_535)); //last offset of the first block
+    assert!(high_half.iter().any(|v| **v == 65_536)); //first offset of the second, partial block
+    assert!(high_half.iter().any(|v| **v == 69_999)); //last offset, inside the partial tail
+
+    let everything = value_index.filter_gte(0);
+    assert_eq!(data.filter(!everything).count(), 0);
+
+    let nothing = value_index.filter_lt(0);
+    assert_eq!(data.filter(!nothing).count(), 70_000);
+}
+
+#[test]
+fn test_filter_prefix() {
+    let data = get_test_data();
+    let first_name_index = data.get_index(|v| v.first_name.to_string());
+    let filtered: Vec<&Person> = data
+        .filter(first_name_index.filter_prefix("Da".to_string()))
+        .collect();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].first_name, "Daniella");
+}
+
+#[test]
+fn test_filter_prefix_no_match() {
+    let data = get_test_data();
+    let first_name_index = data.get_index(|v| v.first_name.to_string());
+    let filtered: Vec<&Person> = data
+        .filter(first_name_index.filter_prefix("Zzz".to_string()))
+        .collect();
+    assert_eq!(filtered.len(), 0);
+}
+
+#[test]
+fn test_filter_fuzzy() {
+    let data = get_test_data();
+    let first_name_index = data.get_index(|v| v.first_name.to_string());
+    //With 0 edits only the exact key matches; allowing 1 edit must still include the exact match.
+    let filtered: Vec<&Person> = data
+        .filter(first_name_index.filter_fuzzy("Haris", 0))
+        .collect();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].first_name, "Haris");
+
+    let filtered_typo: Vec<&Person> = data
+        .filter(first_name_index.filter_fuzzy("Haris", 1))
+        .collect();
+    assert!(filtered_typo.iter().any(|p| p.first_name == "Haris"));
+}
+
+#[test]
+fn test_snapshot_index_does_not_see_later_inserts() {
+    let mut data = get_test_data();
+    let age_index = data.get_index(|v| v.age);
+    data.insert(Person {
+        first_name: "Zoe",
+        last_name: "Young",
+        age: 90,
+    });
+    //The snapshot's complement is still taken over the 10 original items, so the item inserted
+    //afterwards must not show up even though its age doesn't match `filter_lt`.
+    let filtered: Vec<&Person> = data.filter(!age_index.filter_lt(0)).collect();
+    assert!(filtered.iter().all(|p| p.first_name != "Zoe"));
+    assert_eq!(filtered.len(), 10);
+}
+
+#[test]
+fn test_insert_updates_existing_index() {
+    let mut data = get_test_data();
+    let age_index = data.get_live_index(|v| v.age);
+    let new_index = data.insert(Person {
+        first_name: "Zoe",
+        last_name: "Young",
+        age: 90,
+    });
+    assert_eq!(new_index, 10);
+    let filtered: Vec<&Person> = data.filter(age_index.filter_gt(80)).collect();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].first_name, "Zoe");
+}
+
+#[test]
+fn test_remove_updates_existing_index_and_keeps_other_indices_stable() {
+    let mut data = get_test_data();
+    let age_index = data.get_live_index(|v| v.age);
+    let aaron_index = data.items().position(|v| v.first_name == "Aaron").unwrap();
+    data.remove(aaron_index);
+
+    let filtered: Vec<&Person> = data.filter(age_index.filter_lt(20)).collect();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].first_name, "Catherine");
+
+    //Other items keep their original index, so a freshly built index still finds them.
+    let first_name_index = data.get_index(|v| v.first_name);
+    let filtered: Vec<&Person> = data.filter(first_name_index.filter_eq("Isaiah")).collect();
+    assert_eq!(filtered.len(), 1);
+}
+
+#[test]
+fn test_group_by_counts_and_sum() {
+    let data = get_test_data();
+    let age_decade_index = data.get_index(|v| v.age / 10);
+    let grouped = data.group_by(&age_decade_index);
+
+    let counts = grouped.counts(None);
+    assert_eq!(*counts.get(&2).unwrap(), 2); //Haris (28), Daniella (28)
+    assert_eq!(*counts.get(&3).unwrap(), 2); //Isaiah (32), Brett (37)
+
+    let sums = grouped.sum_by(|v| v.age as u64, None);
+    assert_eq!(*sums.get(&2).unwrap(), 28 + 28);
+}
+
+#[test]
+fn test_group_by_min_max_with_filter() {
+    let data = get_test_data();
+    let last_initial_index = data.get_index(|v| v.last_name.chars().next().unwrap());
+    let grouped = data.group_by(&last_initial_index);
+
+    let oldest_per_group = grouped.max_by(|v| v.age, None);
+    assert_eq!(oldest_per_group.get(&'M').unwrap().first_name, "Isaiah");
+
+    let age_index = data.get_index(|v| v.age);
+    let adults = age_index.filter_gte(30);
+    let oldest_adult_per_group = grouped.max_by(|v| v.age, Some(&adults));
+    //Aaron Mcbride (age 8) no longer counts, but Isaiah Mccarthy (32) still does.
+    assert_eq!(
+        oldest_adult_per_group.get(&'M').unwrap().first_name,
+        "Isaiah"
+    );
+}
+
+#[test]
+fn test_filter_ordered() {
+    let data = get_test_data();
+    let age_index = data.get_index(|v| v.age);
+    let last_name_index = data.get_index(|v| v.last_name);
+    let adults = age_index.filter_gte(30);
+
+    let ascending: Vec<&Person> = data
+        .filter_ordered(adults.clone(), &age_index, Order::Ascending)
+        .collect();
+    let ages: Vec<u32> = ascending.iter().map(|p| p.age).collect();
+    assert_eq!(ages, vec![32, 37, 42, 58, 63, 75]);
+
+    let descending: Vec<&Person> = data
+        .filter_ordered(adults, &age_index, Order::Descending)
+        .collect();
+    let ages: Vec<u32> = descending.iter().map(|p| p.age).collect();
+    assert_eq!(ages, vec![75, 63, 58, 42, 37, 32]);
+
+    //Ordering by an index unrelated to the filter still only emits matches.
+    let ordered_by_name: Vec<&Person> = data
+        .filter_ordered(age_index.filter_lt(20), &last_name_index, Order::Ascending)
+        .collect();
+    assert_eq!(ordered_by_name.len(), 2);
+    assert_eq!(ordered_by_name[0].last_name, "Hunt");
+    assert_eq!(ordered_by_name[1].last_name, "Mcbride");
+}
+
+#[test]
+fn test_top_n() {
+    let data = get_test_data();
+    let age_index = data.get_index(|v| v.age);
+
+    let everyone = age_index.filter_gte(0);
+    let oldest: Vec<&Person> = data
+        .top_n(everyone.clone(), &age_index, 3, Order::Descending)
+        .collect();
+    let ages: Vec<u32> = oldest.iter().map(|p| p.age).collect();
+    assert_eq!(ages, vec![75, 63, 58]);
+
+    let youngest: Vec<&Person> = data
+        .top_n(everyone, &age_index, 3, Order::Ascending)
+        .collect();
+    let ages: Vec<u32> = youngest.iter().map(|p| p.age).collect();
+    assert_eq!(ages, vec![8, 16, 28]);
+
+    let adults = age_index.filter_gte(30);
+    let oldest_adults: Vec<&Person> = data
+        .top_n(adults, &age_index, 2, Order::Descending)
+        .collect();
+    let ages: Vec<u32> = oldest_adults.iter().map(|p| p.age).collect();
+    assert_eq!(ages, vec![75, 63]);
+
+    let none: Vec<&Person> = data
+        .top_n(age_index.filter_gt(1000), &age_index, 5, Order::Ascending)
+        .collect();
+    assert_eq!(none.len(), 0);
+}
+
 #[test]
 fn test_first_last() {
     let data = get_test_data();